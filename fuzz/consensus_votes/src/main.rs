@@ -0,0 +1,38 @@
+//! Honggfuzz target: decodes fuzz bytes into synthetic sign/verify inputs and feeds them through
+//! the consensus `Context` signature path, checking that a mutated signature never verifies
+//! against data it wasn't produced over, and that the same validator never signs two conflicting
+//! votes at the same height over the life of this process.
+//!
+//! NOTE: this target is wired against `consensus_fuzzing::fixtures::FixtureContext`, a fixed,
+//! non-secret test keypair. `Context` and `ValidatorSecret` are `pub(crate)`, so this (separate)
+//! crate can't implement them itself; the fixture lives in the main crate instead and is
+//! re-exported under the `fuzzing` feature. Swap it for the node's real `Context` implementation
+//! once one exists and this target is otherwise ready to run as-is.
+
+use arbitrary::Unstructured;
+use casperlabs_node::consensus_fuzzing::{
+    fixtures::{fixture_validator_id, fixture_validator_secret, FixtureContext},
+    fuzz_sign_verify, EquivocationGuard, SignVerifyInput,
+};
+use honggfuzz::fuzz;
+
+fn main() {
+    let context = FixtureContext::default();
+    let secret = fixture_validator_secret();
+    let validator_id = fixture_validator_id(&secret);
+    // Held across the whole process, not per-input: equivocation is a property of the sequence of
+    // votes this validator signs, so the guard has to see every input honggfuzz throws at it.
+    let mut guard = EquivocationGuard::new();
+
+    loop {
+        fuzz!(|data: &[u8]| {
+            let mut u = Unstructured::new(data);
+            let input = match SignVerifyInput::arbitrary_take_rest(u) {
+                Ok(input) => input,
+                Err(_) => return,
+            };
+
+            fuzz_sign_verify(&context, &secret, &validator_id, &mut guard, input);
+        });
+    }
+}
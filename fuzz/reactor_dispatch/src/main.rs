@@ -0,0 +1,36 @@
+//! Honggfuzz target: feeds arbitrary bytes into `reactor::dispatch_event` via the
+//! `reactor::fuzzing` hooks, checking that no input causes a panic and that storage writes never
+//! exceed `max_block_store_size`.
+
+use casperlabs_node::{
+    reactor::{fuzzing::fuzz_dispatch, validator},
+    ApiServerConfig, SmallNetworkConfig, StorageConfig,
+};
+use honggfuzz::fuzz;
+
+fn main() {
+    loop {
+        fuzz!(|data: &[u8]| {
+            let storage_config = StorageConfig::default();
+            let max_block_store_size = storage_config.max_block_store_size;
+
+            fuzz_dispatch::<validator::Reactor>(
+                data,
+                |event_queue| {
+                    validator::Reactor::new(
+                        SmallNetworkConfig::default(),
+                        ApiServerConfig::default(),
+                        storage_config.clone(),
+                        event_queue,
+                    )
+                },
+                |reactor| {
+                    assert!(
+                        reactor.storage_size_on_disk() <= max_block_store_size,
+                        "storage grew beyond max_block_store_size"
+                    );
+                },
+            );
+        });
+    }
+}
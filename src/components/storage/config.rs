@@ -1,11 +1,12 @@
-use std::{io, path::PathBuf};
+use std::path::PathBuf;
 
 use directories::ProjectDirs;
-use libc::{self, _SC_PAGESIZE};
 use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
 use tracing::warn;
 
+use super::backend::{self, Backend, OpenError, OpenedEnvironment};
+
 const QUALIFIER: &str = "io";
 const ORGANIZATION: &str = "CasperLabs";
 const APPLICATION: &str = "casperlabs-node";
@@ -15,6 +16,11 @@ const DEFAULT_MAX_DEPLOY_STORE_SIZE: usize = 322_122_547_200; // 300 GiB
 
 const DEFAULT_TEST_MAX_DB_SIZE: usize = 52_428_800; // 50 MiB
 
+/// Default MDBX growth step: how much the environment grows by each time it runs out of space.
+const DEFAULT_GROWTH_STEP_BYTES: usize = 134_217_728; // 128 MiB
+/// Default maximum number of concurrent reader slots.
+const DEFAULT_MAX_READERS: u32 = 126;
+
 /// On-disk storage configuration.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
@@ -34,14 +40,54 @@ pub struct Config {
     ///
     /// Defaults to 483,183,820,800 == 450 GiB.
     ///
-    /// The size should be a multiple of the OS page size.
+    /// The size should be a multiple of the OS page size (LMDB), or of `growth_step_bytes`
+    /// (MDBX).
     pub max_block_store_size: usize,
     /// Sets the maximum size of the database to use for the deploy store.
     ///
     /// Defaults to 322,122,547,200 == 300 GiB.
     ///
-    /// The size should be a multiple of the OS page size.
+    /// The size should be a multiple of the OS page size (LMDB), or of `growth_step_bytes`
+    /// (MDBX).
     pub max_deploy_store_size: usize,
+    /// The storage engine to open the block and deploy stores against.
+    ///
+    /// Defaults to `Backend::Lmdb`, matching prior behavior. `Backend::Mdbx` offers better write
+    /// amplification at the cost of different geometry constraints (see `growth_step_bytes`
+    /// below); `Backend::InMemory` keeps no on-disk footprint and is intended for tests.
+    #[serde(default)]
+    pub backend: Backend,
+    /// Maximum number of concurrent reader slots the environment allows.
+    ///
+    /// Defaults to 126. Ignored by the in-memory backend.
+    #[serde(default = "default_max_readers")]
+    pub max_readers: u32,
+    /// The amount the environment grows by each time it runs out of mapped space.
+    ///
+    /// Defaults to 134,217,728 == 128 MiB. Only meaningful for the MDBX backend, which requires
+    /// `max_block_store_size` and `max_deploy_store_size` to be a multiple of this value.
+    #[serde(default = "default_growth_step_bytes")]
+    pub growth_step_bytes: usize,
+    /// If `true`, the environment is opened without forcing a filesystem sync on every commit,
+    /// trading durability against a host crash for write throughput.
+    ///
+    /// Defaults to `false`. Ignored by the in-memory backend.
+    #[serde(default)]
+    pub no_sync: bool,
+    /// Number of memory arenas to give the allocator, if the backend supports tuning this.
+    ///
+    /// Defaults to `None`, meaning "use the backend's own default". Ignored by backends that
+    /// don't expose an arena count.
+    #[serde(default)]
+    pub memory_arena_count: Option<usize>,
+}
+
+fn default_max_readers() -> u32 {
+    DEFAULT_MAX_READERS
+}
+
+fn default_growth_step_bytes() -> usize {
+    DEFAULT_GROWTH_STEP_BYTES
 }
 
 impl Config {
@@ -56,25 +102,27 @@ impl Config {
             path,
             max_block_store_size: DEFAULT_TEST_MAX_DB_SIZE,
             max_deploy_store_size: DEFAULT_TEST_MAX_DB_SIZE,
+            backend: Backend::InMemory,
+            max_readers: DEFAULT_MAX_READERS,
+            growth_step_bytes: DEFAULT_GROWTH_STEP_BYTES,
+            no_sync: false,
+            memory_arena_count: None,
         };
         (config, tempdir)
     }
 
-    /// Prints a warning if any max DB size is not a multiple of the OS page size.
+    /// Prints a warning if any configured size isn't valid for `self.backend`'s geometry.
     pub fn check_sizes(&self) {
-        let page_size = get_page_size().unwrap_or(1);
-        if self.max_block_store_size % page_size != 0 {
-            warn!(
-                "max block store DB size {} is not multiple of system page size {}",
-                self.max_block_store_size, page_size
-            );
-        }
-        if self.max_deploy_store_size % page_size != 0 {
-            warn!(
-                "max deploy store DB size {} is not multiple of system page size {}",
-                self.max_deploy_store_size, page_size
-            );
-        }
+        backend::for_backend(self.backend).check_sizes(self);
+    }
+
+    /// Opens `self.backend`'s environment at `self.path`, applying `max_readers`, `no_sync`, and
+    /// `memory_arena_count` as far as the chosen backend supports them.
+    ///
+    /// This is what the block and deploy stores should call to get their environment handle,
+    /// rather than assuming LMDB and constructing one directly.
+    pub(crate) fn open_backend(&self) -> Result<OpenedEnvironment, OpenError> {
+        backend::for_backend(self.backend).open(self)
     }
 }
 
@@ -91,22 +139,14 @@ impl Default for Config {
             path,
             max_block_store_size: DEFAULT_MAX_BLOCK_STORE_SIZE,
             max_deploy_store_size: DEFAULT_MAX_DEPLOY_STORE_SIZE,
+            backend: Backend::default(),
+            max_readers: DEFAULT_MAX_READERS,
+            growth_step_bytes: DEFAULT_GROWTH_STEP_BYTES,
+            no_sync: false,
+            memory_arena_count: None,
         };
 
         config.check_sizes();
         config
     }
 }
-
-/// Returns OS page size
-fn get_page_size() -> Result<usize, io::Error> {
-    // https://www.gnu.org/software/libc/manual/html_node/Sysconf.html
-    let value = unsafe { libc::sysconf(_SC_PAGESIZE) };
-
-    if value < 0 {
-        warn!("unable to get system page size");
-        return Err(io::Error::last_os_error());
-    }
-
-    Ok(value as usize)
-}
@@ -36,6 +36,14 @@ pub use components::{
     small_network::{Config as SmallNetworkConfig, Error as SmallNetworkError},
     storage::{Config as StorageConfig, Error as StorageError},
 };
+/// Fuzzing hooks for the consensus `Context`/`ValidatorSecret` signature path.
+///
+/// `Context` and `ValidatorSecret` themselves stay `pub(crate)` - only the already-pub fuzzing
+/// entry points and fixture types are re-exported here, so `fuzz/consensus_votes` (a separate
+/// crate) never has to name or implement those traits itself; see
+/// `components::consensus::highway_core::fuzzing` for why.
+#[cfg(feature = "fuzzing")]
+pub use components::consensus::highway_core::fuzzing as consensus_fuzzing;
 
 /// The default listening port for the root node of the validator network.
 pub const ROOT_VALIDATOR_LISTENING_PORT: u16 = 34553;
@@ -0,0 +1,88 @@
+//! Fuzzing hooks for reactor event dispatch.
+//!
+//! The honggfuzz targets in `fuzz/` don't understand this crate's event types, only raw bytes.
+//! This module bridges the gap: it turns fuzzer-provided bytes into a sequence of `R::Event`s via
+//! `arbitrary`, then replays them through a single-node [`testing::Network`] so the fuzzer
+//! exercises exactly the same `dispatch_event` path a real node would, including the effects each
+//! event produces.
+//!
+//! Only compiled when fuzzing, since `Arbitrary` is not a bound we want on `Reactor::Event` in
+//! production: events are free to add variants that have no sensible arbitrary decoding (e.g. ones
+//! carrying a live socket).
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{
+    effect::{Effect, Multiple},
+    reactor::{
+        testing::{Network, RoutableEvent},
+        EventQueueHandle, QueueKind, Reactor, Result,
+    },
+};
+
+/// Upper bound on the number of events a single fuzz input can decode into.
+///
+/// `decode_events` already stops once a decode attempt consumes no bytes (e.g. a unit-like `Tick`
+/// variant), so every decoded event costs at least one byte; this cap additionally bounds how many
+/// events a long input can decode into, whatever its length.
+const MAX_DECODED_EVENTS: usize = 4096;
+
+/// Decodes as many `Ev` values as `u` has remaining bytes for, stopping at the first decode
+/// failure rather than erroring the whole run - a malformed tail is far more common from a fuzzer
+/// than a malformed prefix, and discarding the run entirely would waste the bytes that did decode.
+///
+/// Also stops once [`MAX_DECODED_EVENTS`] have been decoded, or if a decode attempt leaves `u` no
+/// shorter than it was before - either of which would otherwise let a single small input loop
+/// effectively forever.
+pub fn decode_events<'a, Ev: Arbitrary<'a>>(u: &mut Unstructured<'a>) -> Vec<Ev> {
+    let mut events = Vec::new();
+    while !u.is_empty() && events.len() < MAX_DECODED_EVENTS {
+        let len_before = u.len();
+        match Ev::arbitrary(u) {
+            Ok(event) => events.push(event),
+            Err(_) => break,
+        }
+        if u.len() >= len_before {
+            break;
+        }
+    }
+    events
+}
+
+/// Feeds `data` through `R` as a sequence of decoded events on a single-node [`Network`], then
+/// calls `check_invariant` after every event is dispatched.
+///
+/// `check_invariant` should assert (panicking on failure, as fuzz targets expect) properties that
+/// must hold regardless of event ordering or content, e.g. "a validator never signs two
+/// conflicting votes at the same height" or "storage writes stay within the configured store
+/// size". A panic here is exactly what honggfuzz looks for: it marks `data` as a crashing input
+/// and saves it for replay.
+pub fn fuzz_dispatch<'a, R>(
+    data: &'a [u8],
+    make_reactor: impl FnOnce(
+        EventQueueHandle<R::Event>,
+    ) -> Result<(R, Multiple<(Effect<R::Event>, QueueKind)>)>,
+    mut check_invariant: impl FnMut(&R),
+) where
+    R: Reactor,
+    R::Event: Arbitrary<'a> + RoutableEvent,
+{
+    let mut u = Unstructured::new(data);
+    let events = decode_events::<R::Event>(&mut u);
+
+    let mut net = Network::<R>::new(0);
+    let event_queue = net.prepare_node(0);
+    let (reactor, initial_effects) = match make_reactor(event_queue) {
+        Ok(built) => built,
+        // A reactor that fails to construct at all isn't something event dispatch can be blamed
+        // for; nothing to fuzz.
+        Err(_) => return,
+    };
+    net.finish_node(0, event_queue, reactor, initial_effects);
+
+    for event in events {
+        net.schedule_event(0, event);
+        net.crank();
+        net.with_reactor(0, &mut check_invariant);
+    }
+}